@@ -1,99 +1,391 @@
+use std::collections::{HashMap, VecDeque};
+
 const INPUT: &'static str = include_str!("./input");
 
-fn part1() {
-    let mut total = 0;
+// The zero of every run of ten Unicode decimal digits (general category Nd), as of Unicode 14
+const UNICODE_ZEROS: [char; 66] = [
+    '\u{30}', '\u{660}', '\u{6F0}', '\u{7C0}', '\u{966}', '\u{9E6}', '\u{A66}', '\u{AE6}',
+    '\u{B66}', '\u{BE6}', '\u{C66}', '\u{CE6}', '\u{D66}', '\u{DE6}', '\u{E50}', '\u{ED0}',
+    '\u{F20}', '\u{1040}', '\u{1090}', '\u{17E0}', '\u{1810}', '\u{1946}', '\u{19D0}', '\u{1A80}',
+    '\u{1A90}', '\u{1B50}', '\u{1BB0}', '\u{1C40}', '\u{1C50}', '\u{A620}', '\u{A8D0}', '\u{A900}',
+    '\u{A9D0}', '\u{A9F0}', '\u{AA50}', '\u{ABF0}', '\u{FF10}', '\u{104A0}', '\u{10D30}', '\u{11066}',
+    '\u{110F0}', '\u{11136}', '\u{111D0}', '\u{112F0}', '\u{11450}', '\u{114D0}', '\u{11650}', '\u{116C0}',
+    '\u{11730}', '\u{118E0}', '\u{11950}', '\u{11C50}', '\u{11D50}', '\u{11DA0}', '\u{16A60}', '\u{16AC0}',
+    '\u{16B50}', '\u{1D7CE}', '\u{1D7D8}', '\u{1D7E2}', '\u{1D7EC}', '\u{1D7F6}', '\u{1E140}', '\u{1E2F0}',
+    '\u{1E950}', '\u{1FBF0}',
+];
 
-    for line in INPUT.lines() {
-        let mut first = None;
-        let mut last = None;
-        for c in line.chars() {
-            if let Some(n) = c.to_digit(10) {
-                if first == None {
-                    first = Some(n);
-                }
+#[derive(Debug, Clone)]
+struct Vocabulary {
+    words: Vec<(String, u32)>,
+}
+
+impl Vocabulary {
+    fn new(words: &[(&str, u32)]) -> Vocabulary {
+        Vocabulary {
+            words: words.iter().map(|&(w, v)| (w.to_string(), v)).collect(),
+        }
+    }
+
+    fn digits() -> Vocabulary {
+        Vocabulary::radix(10)
+    }
 
-                last = Some(n);
+    fn radix(radix: u32) -> Vocabulary {
+        let digits = ('0'..='9').chain('a'..='z').chain('A'..='Z');
+
+        Vocabulary {
+            words: digits
+                .filter_map(|c| Some((c.to_string(), c.to_digit(radix)?)))
+                .collect(),
+        }
+    }
+
+    fn unicode_digits() -> Vocabulary {
+        let mut words = vec![];
+
+        for zero in UNICODE_ZEROS {
+            for n in 0..10 {
+                let c = char::from_u32(zero as u32 + n).unwrap();
+                words.push((c.to_string(), n));
             }
         }
 
-        total += first.unwrap() * 10 + last.unwrap();
+        Vocabulary { words }
     }
 
-    println!("Part 1: {}", total);
+    fn digits_from_args() -> Vocabulary {
+        let mut vocabulary = Vocabulary::digits();
+
+        for arg in std::env::args().skip(1) {
+            if arg == "--unicode" {
+                vocabulary = Vocabulary::unicode_digits();
+            } else if let Some(radix) = arg.strip_prefix("--radix=") {
+                let radix = radix.parse().expect("radix must be a number");
+                assert!((2..=36).contains(&radix), "radix must be between 2 and 36");
+                vocabulary = Vocabulary::radix(radix);
+            }
+        }
+
+        vocabulary
+    }
+
+    fn english() -> Vocabulary {
+        Vocabulary::new(&[
+            ("one", 1),
+            ("two", 2),
+            ("three", 3),
+            ("four", 4),
+            ("five", 5),
+            ("six", 6),
+            ("seven", 7),
+            ("eight", 8),
+            ("nine", 9),
+        ])
+    }
+
+    fn zero() -> Vocabulary {
+        Vocabulary::new(&[("zero", 0)])
+    }
+
+    fn teens() -> Vocabulary {
+        Vocabulary::new(&[
+            ("ten", 10),
+            ("eleven", 11),
+            ("twelve", 12),
+            ("thirteen", 13),
+            ("fourteen", 14),
+            ("fifteen", 15),
+            ("sixteen", 16),
+            ("seventeen", 17),
+            ("eighteen", 18),
+            ("nineteen", 19),
+        ])
+    }
+
+    fn with(mut self, mut other: Vocabulary) -> Vocabulary {
+        self.words.append(&mut other.words);
+        self
+    }
 }
 
-fn part2() {
-    let mut total = 0;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Match {
+    position: usize,
+    length: usize,
+    value: u32,
+}
 
-    for mut line in INPUT.lines() {
-        let mut first = None;
-        let mut last = None;
+#[derive(Debug, Default)]
+struct Node {
+    next: HashMap<char, usize>,
+    fail: usize,
+    // (length in bytes, value) of every word ending at this node
+    outputs: Vec<(usize, u32)>,
+}
 
-        while line.len() > 0 {
-            if let Some(n) = line.chars().next().unwrap().to_digit(10) {
-                if first == None {
-                    first = Some(n);
-                }
+// Aho-Corasick automaton over a set of words
+#[derive(Debug)]
+struct Automaton {
+    nodes: Vec<Node>,
+}
 
-                last = Some(n);
-            } else if line.starts_with("one") {
-                if first == None {
-                    first = Some(1);
-                }
-                last = Some(1);
-            } else if line.starts_with("two") {
-                if first == None {
-                    first = Some(2);
-                }
-                last = Some(2);
-            } else if line.starts_with("three") {
-                if first == None {
-                    first = Some(3);
-                }
-                last = Some(3);
-            } else if line.starts_with("four") {
-                if first == None {
-                    first = Some(4);
-                }
-                last = Some(4);
-            } else if line.starts_with("five") {
-                if first == None {
-                    first = Some(5);
-                }
-                last = Some(5);
-            } else if line.starts_with("six") {
-                if first == None {
-                    first = Some(6);
-                }
-                last = Some(6);
-            } else if line.starts_with("seven") {
-                if first == None {
-                    first = Some(7);
-                }
-                last = Some(7);
-            } else if line.starts_with("eight") {
-                if first == None {
-                    first = Some(8);
+impl Automaton {
+    fn new(words: impl Iterator<Item = (String, u32)>) -> Automaton {
+        let mut nodes = vec![Node::default()];
+
+        for (word, value) in words {
+            let mut state = 0;
+
+            for c in word.chars() {
+                state = match nodes[state].next.get(&c) {
+                    Some(&next) => next,
+                    None => {
+                        nodes.push(Node::default());
+                        let next = nodes.len() - 1;
+                        nodes[state].next.insert(c, next);
+                        next
+                    }
+                };
+            }
+
+            nodes[state].outputs.push((word.len(), value));
+        }
+
+        let mut queue: VecDeque<usize> = nodes[0].next.values().cloned().collect();
+
+        while let Some(state) = queue.pop_front() {
+            let children: Vec<_> = nodes[state].next.iter().map(|(&c, &n)| (c, n)).collect();
+
+            for (c, child) in children {
+                let mut fail = nodes[state].fail;
+                while fail != 0 && !nodes[fail].next.contains_key(&c) {
+                    fail = nodes[fail].fail;
                 }
-                last = Some(8);
-            } else if line.starts_with("nine") {
-                if first == None {
-                    first = Some(9);
+                let fail = nodes[fail].next.get(&c).cloned().unwrap_or(0);
+
+                nodes[child].fail = fail;
+                let mut inherited = nodes[fail].outputs.clone();
+                nodes[child].outputs.append(&mut inherited);
+
+                queue.push_back(child);
+            }
+        }
+
+        Automaton { nodes }
+    }
+
+    fn step(&self, mut state: usize, c: char) -> usize {
+        loop {
+            if let Some(&next) = self.nodes[state].next.get(&c) {
+                return next;
+            }
+
+            if state == 0 {
+                return 0;
+            }
+
+            state = self.nodes[state].fail;
+        }
+    }
+
+    fn outputs(&self, state: usize) -> &[(usize, u32)] {
+        &self.nodes[state].outputs
+    }
+}
+
+#[derive(Debug)]
+struct Scanner {
+    forward: Automaton,
+    // Built from the reversed words, so it can be run from the end of a line
+    backward: Automaton,
+    longest: usize,
+}
+
+impl Scanner {
+    fn new(vocabulary: &Vocabulary) -> Scanner {
+        let words = vocabulary.words.iter().cloned();
+        let reversed = vocabulary
+            .words
+            .iter()
+            .map(|(w, v)| (w.chars().rev().collect(), *v));
+
+        Scanner {
+            forward: Automaton::new(words),
+            backward: Automaton::new(reversed),
+            longest: vocabulary.words.iter().map(|(w, _)| w.len()).max().unwrap_or(0),
+        }
+    }
+
+    fn first(&self, line: &str) -> Option<Match> {
+        let mut first: Option<Match> = None;
+        let mut state = 0;
+
+        for (i, c) in line.char_indices() {
+            // Nothing ending from here on can start before the best match so far
+            if first.is_some_and(|m| i >= m.position + self.longest) {
+                break;
+            }
+
+            state = self.forward.step(state, c);
+
+            let end = i + c.len_utf8();
+            // Of the words starting at the same position, the longest wins
+            for &(length, value) in self.forward.outputs(state) {
+                let position = end - length;
+                if first.is_none_or(|m| (position, m.length) < (m.position, length)) {
+                    first = Some(Match {
+                        position,
+                        length,
+                        value,
+                    });
                 }
-                last = Some(9);
             }
+        }
+
+        first
+    }
+
+    fn last(&self, line: &str) -> Option<Match> {
+        let mut state = 0;
+
+        for (i, c) in line.char_indices().rev() {
+            state = self.backward.step(state, c);
 
-            let (left, right) = line.split_at(1);
-            line = right;
+            let longest = self.backward.outputs(state).iter().max_by_key(|o| o.0);
+            if let Some(&(length, value)) = longest {
+                return Some(Match {
+                    position: i,
+                    length,
+                    value,
+                });
+            }
         }
 
-        total += first.unwrap() * 10 + last.unwrap();
+        None
+    }
+
+    fn calibration_value(&self, line: &str) -> Option<u32> {
+        let first = self.first(line)?;
+        let last = self.last(line)?;
+
+        Some(first.value * 10 + last.value)
     }
 
-    println!("Part 2: {}", total);
+    fn calibrate(&self, input: &str, policy: MissingDigits) -> Result<Calibration, MissingDigitsError> {
+        let mut total = 0;
+        let mut missing = vec![];
+
+        for (i, line) in input.lines().enumerate() {
+            match self.calibration_value(line) {
+                Some(value) => total += value,
+                None => match policy {
+                    MissingDigits::Error => return Err(MissingDigitsError { line: i + 1 }),
+                    MissingDigits::Skip | MissingDigits::Zero => missing.push(i + 1),
+                },
+            }
+        }
+
+        Ok(Calibration {
+            total,
+            policy,
+            missing,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MissingDigits {
+    Error,
+    Skip,
+    Zero,
+}
+
+impl MissingDigits {
+    fn from_args() -> MissingDigits {
+        let mut policy = MissingDigits::Error;
+
+        for arg in std::env::args().skip(1) {
+            match arg.as_str() {
+                "--missing=error" => policy = MissingDigits::Error,
+                "--missing=skip" => policy = MissingDigits::Skip,
+                "--missing=zero" => policy = MissingDigits::Zero,
+                _ => {}
+            }
+        }
+
+        policy
+    }
+}
+
+#[derive(Debug)]
+struct MissingDigitsError {
+    line: usize,
+}
+
+impl std::fmt::Display for MissingDigitsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {} has no digits", self.line)
+    }
+}
+
+#[derive(Debug)]
+struct Calibration {
+    total: u32,
+    policy: MissingDigits,
+    // 1-based numbers of the lines without digits
+    missing: Vec<usize>,
+}
+
+impl Calibration {
+    fn print(&self, part: usize) {
+        println!("Part {}: {}", part, self.total);
+
+        if self.missing.is_empty() {
+            return;
+        }
+
+        let lines: Vec<_> = self.missing.iter().map(|l| l.to_string()).collect();
+        let action = match self.policy {
+            MissingDigits::Skip => "skipped",
+            _ => "counted as zero",
+        };
+
+        println!("  lines without digits ({}): {}", action, lines.join(", "));
+    }
+}
+
+fn part1(policy: MissingDigits) {
+    let scanner = Scanner::new(&Vocabulary::digits_from_args());
+
+    match scanner.calibrate(INPUT, policy) {
+        Ok(calibration) => calibration.print(1),
+        Err(e) => println!("Part 1: {}", e),
+    }
+}
+
+fn part2(policy: MissingDigits) {
+    let mut vocabulary = Vocabulary::digits_from_args().with(Vocabulary::english());
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--zero" => vocabulary = vocabulary.with(Vocabulary::zero()),
+            "--teens" => vocabulary = vocabulary.with(Vocabulary::teens()),
+            _ => {}
+        }
+    }
+
+    let scanner = Scanner::new(&vocabulary);
+
+    match scanner.calibrate(INPUT, policy) {
+        Ok(calibration) => calibration.print(2),
+        Err(e) => println!("Part 2: {}", e),
+    }
 }
 
 fn main() {
-    part1();
-    part2();
+    let policy = MissingDigits::from_args();
+
+    part1(policy);
+    part2(policy);
 }
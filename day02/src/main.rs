@@ -1,38 +1,72 @@
+use std::collections::BTreeMap;
+
 const INPUT: &'static str = include_str!("./input");
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone, Default)]
 struct Cubeset {
-    red: usize,
-    green: usize,
-    blue: usize,
+    counts: Vec<(String, usize)>,
 }
 
 impl Cubeset {
-    fn parse(str: &str) -> Cubeset {
-        let mut red = 0;
-        let mut green = 0;
-        let mut blue = 0;
+    // In strict mode a colour named twice is an error, otherwise its counts are added
+    fn parse(str: &str, strict: bool) -> Result<Cubeset, String> {
+        let mut set = Cubeset::default();
 
         for s in str.split(", ") {
             let (n, c) = s.split_once(" ").unwrap();
 
             let v = usize::from_str_radix(n, 10).unwrap();
 
-            match c {
-                "red" => red += v,
-                "green" => green += v,
-                "blue" => blue += v,
-                _ => {
-                    panic!()
-                }
+            if strict && set.counts.iter().any(|(colour, _)| colour == c) {
+                return Err(c.to_string());
             }
+
+            set.add(c, v);
         }
 
-        Cubeset { red, green, blue }
+        Ok(set)
+    }
+
+    fn add(&mut self, colour: &str, count: usize) {
+        match self.counts.iter_mut().find(|(c, _)| c == colour) {
+            Some((_, v)) => *v += count,
+            None => self.counts.push((colour.to_string(), count)),
+        }
+    }
+
+    fn get(&self, colour: &str) -> usize {
+        self.counts
+            .iter()
+            .find(|(c, _)| c == colour)
+            .map_or(0, |&(_, v)| v)
+    }
+
+    fn total(&self) -> usize {
+        self.counts.iter().map(|&(_, v)| v).sum()
+    }
+
+    fn to_json(&self) -> String {
+        let counts: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(c, v)| format!("{}: {}", json_string(c), v))
+            .collect();
+        format!("{{{}}}", counts.join(", "))
+    }
+
+    fn raise_to(&mut self, colour: &str, count: usize) {
+        match self.counts.iter_mut().find(|(c, _)| c == colour) {
+            Some((_, v)) => *v = usize::max(*v, count),
+            None => self.counts.push((colour.to_string(), count)),
+        }
+    }
+
+    fn within(&self, bag: &Cubeset) -> bool {
+        self.counts.iter().all(|(c, v)| *v <= bag.get(c))
     }
 
     fn power(&self) -> usize {
-        self.red * self.green * self.blue
+        self.counts.iter().map(|&(_, v)| v).product()
     }
 }
 
@@ -42,56 +76,401 @@ struct Game {
     sets: Vec<Cubeset>,
 }
 
+impl std::fmt::Display for Cubeset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (c, v)) in self.counts.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} {}", v, c)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct DuplicateColour {
+    game: usize,
+    draw: usize,
+    colour: String,
+}
+
+impl std::fmt::Display for DuplicateColour {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "game {}, draw {}: {} appears more than once",
+            self.game, self.draw, self.colour
+        )
+    }
+}
+
 impl Game {
-    fn parse(str: &str) -> Game {
+    fn parse(str: &str, strict: bool) -> Result<Game, DuplicateColour> {
         let (game_id, sets) = str.split_once(": ").unwrap();
 
         let id = usize::from_str_radix(game_id.split_once(" ").unwrap().1, 10).unwrap();
 
-        Game {
-            id,
-            sets: sets.split("; ").map(Cubeset::parse).collect(),
+        let sets = sets
+            .split("; ")
+            .enumerate()
+            .map(|(i, s)| {
+                Cubeset::parse(s, strict).map_err(|colour| DuplicateColour {
+                    game: id,
+                    draw: i + 1,
+                    colour,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Game { id, sets })
+    }
+
+    fn possible(&self, bag: &Cubeset) -> bool {
+        self.sets.iter().all(|set| set.within(bag))
+    }
+
+    // Every colour in `colours` is included, at zero if the game never draws it
+    fn smallest(&self, colours: &[String]) -> Cubeset {
+        let mut smallest = Cubeset::default();
+
+        for colour in colours {
+            smallest.raise_to(colour, 0);
+        }
+
+        for set in &self.sets {
+            for (colour, count) in &set.counts {
+                smallest.raise_to(colour, *count);
+            }
+        }
+
+        smallest
+    }
+
+    // 1-based draw numbers paired with the colours that exceed the bag in that draw
+    fn failures(&self, bag: &Cubeset) -> Vec<(usize, Vec<String>)> {
+        let mut failures = vec![];
+
+        for (i, set) in self.sets.iter().enumerate() {
+            let exceeded: Vec<_> = set
+                .counts
+                .iter()
+                .filter(|(c, v)| *v > bag.get(c))
+                .map(|(c, _)| c.clone())
+                .collect();
+
+            if !exceeded.is_empty() {
+                failures.push((i + 1, exceeded));
+            }
+        }
+
+        failures
+    }
+}
+
+impl std::fmt::Display for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Game {}: ", self.id)?;
+
+        for (i, set) in self.sets.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", set)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_games() -> Result<Vec<Game>, String> {
+    let strict = std::env::args().any(|a| a == "--strict");
+
+    INPUT
+        .lines()
+        .enumerate()
+        .map(|(i, l)| Game::parse(l, strict).map_err(|e| format!("line {}, {}", i + 1, e)))
+        .collect()
+}
+
+fn colours(games: &[Game]) -> Vec<String> {
+    let mut colours: Vec<String> = vec![];
+
+    for set in games.iter().flat_map(|g| &g.sets) {
+        for (c, _) in &set.counts {
+            if !colours.contains(c) {
+                colours.push(c.clone());
+            }
         }
     }
 
-    fn possible(&self, red: usize, green: usize, blue: usize) -> bool {
-        self.sets
+    colours
+}
+
+fn minimal_bag(games: &[Game]) -> Cubeset {
+    let colours = colours(games);
+    let mut bag = Cubeset::default();
+
+    for game in games {
+        for (colour, count) in &game.smallest(&colours).counts {
+            bag.raise_to(colour, *count);
+        }
+    }
+
+    bag
+}
+
+// For each colour, how many draws showed each number of cubes of that colour
+fn distributions(games: &[Game]) -> Vec<(String, BTreeMap<usize, usize>)> {
+    colours(games)
+        .into_iter()
+        .map(|colour| {
+            let mut histogram = BTreeMap::new();
+
+            for set in games.iter().flat_map(|g| &g.sets) {
+                if let Some((_, count)) = set.counts.iter().find(|(c, _)| *c == colour) {
+                    *histogram.entry(*count).or_insert(0) += 1;
+                }
+            }
+
+            (colour, histogram)
+        })
+        .collect()
+}
+
+// The bags with at most `budget` cubes that make the most games possible. A game is possible
+// exactly when the bag covers its smallest set, so only those counts need to be tried.
+fn best_bags(games: &[Game], budget: usize) -> (usize, Vec<Cubeset>) {
+    let colours = colours(games);
+    let smallest: Vec<_> = games.iter().map(|g| g.smallest(&colours)).collect();
+
+    let candidates: Vec<Vec<usize>> = colours
+        .iter()
+        .map(|c| {
+            let mut counts: Vec<_> = smallest.iter().map(|s| s.get(c)).collect();
+            counts.push(0);
+            counts.sort();
+            counts.dedup();
+            counts
+        })
+        .collect();
+
+    let mut best = 0;
+    let mut bags: Vec<Vec<usize>> = vec![];
+
+    let mut stack = vec![vec![]];
+    while let Some(chosen) = stack.pop() {
+        if chosen.len() < colours.len() {
+            let used: usize = chosen.iter().sum();
+
+            for &count in &candidates[chosen.len()] {
+                if used + count <= budget {
+                    let mut next = chosen.clone();
+                    next.push(count);
+                    stack.push(next);
+                }
+            }
+
+            continue;
+        }
+
+        let possible = smallest
             .iter()
-            .all(|set| set.red <= red && set.green <= green && set.blue <= blue)
+            .filter(|s| colours.iter().zip(&chosen).all(|(c, &n)| s.get(c) <= n))
+            .count();
+
+        if possible > best {
+            best = possible;
+            bags.clear();
+        }
+
+        if possible == best {
+            bags.push(chosen);
+        }
     }
 
-    fn smallest(&self) -> Cubeset {
-        Cubeset {
-            red: self.sets.iter().map(|s| s.red).max().unwrap(),
-            green: self.sets.iter().map(|s| s.green).max().unwrap(),
-            blue: self.sets.iter().map(|s| s.blue).max().unwrap(),
+    // Drop bags that only add cubes to another equally good bag
+    let minimal: Vec<_> = bags
+        .iter()
+        .filter(|bag| {
+            !bags
+                .iter()
+                .any(|other| other != *bag && other.iter().zip(bag.iter()).all(|(o, b)| o <= b))
+        })
+        .map(|bag| Cubeset {
+            counts: colours.iter().cloned().zip(bag.iter().cloned()).collect(),
+        })
+        .collect();
+
+    (best, minimal)
+}
+
+fn default_bag() -> Cubeset {
+    Cubeset::parse("12 red, 13 green, 14 blue", true).unwrap()
+}
+
+fn json_string(str: &str) -> String {
+    format!("\"{}\"", str.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn analyse(games: &[Game]) {
+    let mut bag = default_bag();
+    let mut budget = None;
+    let mut json = false;
+
+    for arg in std::env::args().skip(2) {
+        if let Some(b) = arg.strip_prefix("--bag=") {
+            bag = Cubeset::parse(b, true).expect("bag names a colour more than once");
+        } else if let Some(b) = arg.strip_prefix("--budget=") {
+            budget = Some(b.parse().expect("budget must be a number"));
+        } else if arg == "--json" {
+            json = true;
         }
     }
+
+    let budget = budget.unwrap_or(bag.total());
+
+    let minimal = minimal_bag(games);
+    let failures: Vec<_> = games
+        .iter()
+        .map(|g| (g.id, g.failures(&bag)))
+        .filter(|(_, f)| !f.is_empty())
+        .collect();
+    let distributions = distributions(games);
+    let (possible, best) = best_bags(games, budget);
+
+    if json {
+        let failures: Vec<_> = failures
+            .iter()
+            .map(|(id, draws)| {
+                let draws: Vec<_> = draws
+                    .iter()
+                    .map(|(draw, colours)| {
+                        let colours: Vec<_> = colours.iter().map(|c| json_string(c)).collect();
+                        format!("{{\"draw\": {}, \"colours\": [{}]}}", draw, colours.join(", "))
+                    })
+                    .collect();
+                format!("{{\"game\": {}, \"draws\": [{}]}}", id, draws.join(", "))
+            })
+            .collect();
+
+        let distributions: Vec<_> = distributions
+            .iter()
+            .map(|(colour, histogram)| {
+                let counts: Vec<_> = histogram
+                    .iter()
+                    .map(|(count, draws)| format!("\"{}\": {}", count, draws))
+                    .collect();
+                format!(
+                    "{{\"colour\": {}, \"histogram\": {{{}}}}}",
+                    json_string(colour),
+                    counts.join(", ")
+                )
+            })
+            .collect();
+
+        let best: Vec<_> = best.iter().map(|b| b.to_json()).collect();
+
+        println!("{{");
+        println!("  \"minimal_bag\": {},", minimal.to_json());
+        println!("  \"bag\": {},", bag.to_json());
+        println!("  \"failures\": [{}],", failures.join(", "));
+        println!("  \"distributions\": [{}],", distributions.join(", "));
+        println!(
+            "  \"best_bags\": {{\"budget\": {}, \"possible\": {}, \"bags\": [{}]}}",
+            budget,
+            possible,
+            best.join(", ")
+        );
+        println!("}}");
+
+        return;
+    }
+
+    println!("Minimal bag: {}", minimal);
+
+    println!();
+    println!("Impossible games with {}:", bag);
+    for (id, draws) in &failures {
+        let draws: Vec<_> = draws
+            .iter()
+            .map(|(draw, colours)| format!("draw {} ({})", draw, colours.join(", ")))
+            .collect();
+        println!("  Game {}: {}", id, draws.join("; "));
+    }
+
+    println!();
+    println!("{:<10} {:>6} {:>6} {:>6} {:>8}", "Colour", "Draws", "Min", "Max", "Mean");
+    for (colour, histogram) in &distributions {
+        let draws: usize = histogram.values().sum();
+        let cubes: usize = histogram.iter().map(|(count, draws)| count * draws).sum();
+        println!(
+            "{:<10} {:>6} {:>6} {:>6} {:>8.2}",
+            colour,
+            draws,
+            histogram.keys().next().unwrap(),
+            histogram.keys().last().unwrap(),
+            cubes as f64 / draws as f64
+        );
+    }
+
+    println!();
+    println!("Best bags with at most {} cubes ({} games possible):", budget, possible);
+    for b in &best {
+        println!("  {}", b);
+    }
 }
 
-fn part1() {
-    let mut total = 0;
-    for line in INPUT.lines() {
-        let game = Game::parse(line);
+fn rewrite(games: &[Game]) {
+    for game in games {
+        println!("{}", game);
+    }
+}
 
-        if game.possible(12, 13, 14) {
+fn part1(games: &[Game]) {
+    let bag = default_bag();
+
+    let mut total = 0;
+    for game in games {
+        if game.possible(&bag) {
             total += game.id;
         }
     }
     println!("Part 1: {}", total);
 }
 
-fn part2() {
-    let mut total = 0;
-    for line in INPUT.lines() {
-        let game = Game::parse(line);
+fn part2(games: &[Game]) {
+    // A colour in the bag that a game never draws makes its power zero
+    let mut palette: Vec<_> = default_bag().counts.into_iter().map(|(c, _)| c).collect();
+    for colour in colours(games) {
+        if !palette.contains(&colour) {
+            palette.push(colour);
+        }
+    }
 
-        total += game.smallest().power();
+    let mut total = 0;
+    for game in games {
+        total += game.smallest(&palette).power();
     }
     println!("Part 2: {}", total);
 }
 
 fn main() {
-    part1();
-    part2();
+    let games = match parse_games() {
+        Ok(games) => games,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    match std::env::args().nth(1).as_deref() {
+        Some("analyse") => analyse(&games),
+        Some("rewrite") => rewrite(&games),
+        _ => {
+            part1(&games);
+            part2(&games);
+        }
+    }
 }
@@ -1,5 +1,3 @@
-use std::collections::HashSet;
-
 const INPUT: &'static str = include_str!("./input");
 
 #[derive(Debug, Clone, Copy)]
@@ -9,32 +7,318 @@ enum Value {
     Empty,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Position {
+    row: usize,
+    col: usize,
+}
+
+// A part number and the cells its digits occupy, from `start` to `end` inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    row: usize,
+    start: usize,
+    end: usize,
+    value: usize,
+}
+
+impl Span {
+    fn contains(&self, pos: Position) -> bool {
+        pos.row == self.row && pos.col >= self.start && pos.col <= self.end
+    }
+}
+
 #[derive(Debug)]
 struct Schematic {
     width: usize,
     height: usize,
-    numbers: Vec<usize>,
+    numbers: Vec<Span>,
     values: Vec<Vec<Value>>,
 }
 
+impl Schematic {
+    fn get(&self, pos: Position) -> Option<Value> {
+        self.values.get(pos.row)?.get(pos.col).copied()
+    }
+
+    fn neighbours(&self, pos: Position) -> Vec<Position> {
+        let mut neighbours = vec![];
+
+        for row in pos.row.saturating_sub(1)..=usize::min(pos.row + 1, self.height - 1) {
+            for col in pos.col.saturating_sub(1)..=usize::min(pos.col + 1, self.width - 1) {
+                if (row, col) != (pos.row, pos.col) {
+                    neighbours.push(Position { row, col });
+                }
+            }
+        }
+
+        neighbours
+    }
+
+    fn span_at(&self, pos: Position) -> Option<Span> {
+        match self.get(pos) {
+            Some(Value::Digit { num_id, .. }) => Some(self.numbers[num_id]),
+            _ => None,
+        }
+    }
+
+    fn span_neighbours(&self, span: &Span) -> Vec<Position> {
+        let mut neighbours = vec![];
+
+        for col in span.start..=span.end {
+            for pos in self.neighbours(Position { row: span.row, col }) {
+                if !span.contains(pos) && !neighbours.contains(&pos) {
+                    neighbours.push(pos);
+                }
+            }
+        }
+
+        neighbours.sort();
+
+        neighbours
+    }
+
+    // Numbers with no symbol in any neighbouring cell
+    fn isolated_numbers(&self) -> Vec<Span> {
+        self.numbers
+            .iter()
+            .filter(|span| {
+                self.span_neighbours(span)
+                    .into_iter()
+                    .all(|pos| !matches!(self.get(pos), Some(Value::Symbol(_))))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+// Bipartite graph joining each symbol to the part numbers around it
+#[derive(Debug)]
+struct Graph {
+    symbols: Vec<(char, Position)>,
+    numbers: Vec<Span>,
+    // (symbol index, number index)
+    edges: Vec<(usize, usize)>,
+}
+
+impl From<&Schematic> for Graph {
+    fn from(schematic: &Schematic) -> Self {
+        let mut symbols = vec![];
+        let mut edges = vec![];
+
+        for row in 0..schematic.height {
+            for col in 0..schematic.width {
+                let pos = Position { row, col };
+
+                if let Some(Value::Symbol(c)) = schematic.get(pos) {
+                    let mut num_ids: Vec<_> = schematic
+                        .neighbours(pos)
+                        .into_iter()
+                        .filter_map(|n| match schematic.get(n) {
+                            Some(Value::Digit { num_id, .. }) => Some(num_id),
+                            _ => None,
+                        })
+                        .collect();
+                    num_ids.sort();
+                    num_ids.dedup();
+
+                    for num_id in num_ids {
+                        edges.push((symbols.len(), num_id));
+                    }
+
+                    symbols.push((c, pos));
+                }
+            }
+        }
+
+        Graph {
+            symbols,
+            numbers: schematic.numbers.clone(),
+            edges,
+        }
+    }
+}
+
+impl Graph {
+    fn symbol_numbers(&self, symbol: usize) -> Vec<usize> {
+        self.edges
+            .iter()
+            .filter(|e| e.0 == symbol)
+            .map(|e| self.numbers[e.1].value)
+            .collect()
+    }
+
+    // The sum of the numbers next to at least one symbol
+    fn part_number_total(&self) -> usize {
+        self.numbers
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| self.edges.iter().any(|e| e.1 == i))
+            .map(|(_, n)| n.value)
+            .sum()
+    }
+
+    fn gear_total(&self, rule: &GearRule) -> usize {
+        let mut total = 0;
+
+        for (i, &(c, _)) in self.symbols.iter().enumerate() {
+            let numbers = self.symbol_numbers(i);
+
+            if rule.matches(c, &numbers) {
+                total += rule.value(&numbers);
+            }
+        }
+
+        total
+    }
+
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("graph schematic {\n");
+
+        for (i, (c, pos)) in self.symbols.iter().enumerate() {
+            let c = match c {
+                '"' => "\\\"".to_string(),
+                '\\' => "\\\\".to_string(),
+                c => c.to_string(),
+            };
+            dot += &format!(
+                "    s{} [label=\"{} ({}, {})\", shape=box];\n",
+                i, c, pos.row, pos.col
+            );
+        }
+
+        for (i, span) in self.numbers.iter().enumerate() {
+            dot += &format!(
+                "    n{} [label=\"{} ({}, {}-{})\"];\n",
+                i, span.value, span.row, span.start, span.end
+            );
+        }
+
+        for (symbol, number) in &self.edges {
+            dot += &format!("    s{} -- n{};\n", symbol, number);
+        }
+
+        dot += "}\n";
+
+        dot
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Count {
+    Exactly(usize),
+    AtLeast(usize),
+    AtMost(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Aggregate {
+    Product,
+    Sum,
+    Max,
+}
+
+// Which symbols count as gears, how many numbers they need and how those numbers are combined
+#[derive(Debug, Clone)]
+struct GearRule {
+    symbols: Vec<char>,
+    count: Count,
+    aggregate: Aggregate,
+}
+
+impl Default for GearRule {
+    fn default() -> Self {
+        GearRule {
+            symbols: vec!['*'],
+            count: Count::Exactly(2),
+            aggregate: Aggregate::Product,
+        }
+    }
+}
+
+impl GearRule {
+    fn from_args() -> GearRule {
+        let mut rule = GearRule::default();
+
+        for arg in std::env::args().skip(1) {
+            let Some((key, value)) = arg.split_once('=') else {
+                continue;
+            };
+
+            match key {
+                "--symbols" => rule.symbols = value.chars().collect(),
+                "--exactly" => rule.count = Count::Exactly(value.parse().unwrap()),
+                "--at-least" => rule.count = Count::AtLeast(value.parse().unwrap()),
+                "--at-most" => rule.count = Count::AtMost(value.parse().unwrap()),
+                "--aggregate" => {
+                    rule.aggregate = match value {
+                        "product" => Aggregate::Product,
+                        "sum" => Aggregate::Sum,
+                        "max" => Aggregate::Max,
+                        _ => panic!("unknown aggregate: {}", value),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        rule
+    }
+
+    fn matches(&self, symbol: char, numbers: &[usize]) -> bool {
+        let count_matches = match self.count {
+            Count::Exactly(n) => numbers.len() == n,
+            Count::AtLeast(n) => numbers.len() >= n,
+            Count::AtMost(n) => numbers.len() <= n,
+        };
+
+        self.symbols.contains(&symbol) && count_matches
+    }
+
+    // A gear with no numbers is worth nothing, whatever the aggregate
+    fn value(&self, numbers: &[usize]) -> usize {
+        if numbers.is_empty() {
+            return 0;
+        }
+
+        match self.aggregate {
+            Aggregate::Product => numbers.iter().product(),
+            Aggregate::Sum => numbers.iter().sum(),
+            Aggregate::Max => *numbers.iter().max().unwrap(),
+        }
+    }
+}
+
 fn parse() -> Schematic {
-    let width = INPUT.lines().next().unwrap().chars().count();
-    let height = INPUT.lines().count();
+    parse_str(INPUT)
+}
+
+fn parse_str(input: &str) -> Schematic {
+    let width = input.lines().next().unwrap().chars().count();
+    let height = input.lines().count();
     let mut numbers = vec![];
 
-    let values = INPUT
+    let values = input
         .lines()
-        .map(|l| {
-            let mut current_number = None;
+        .enumerate()
+        .map(|(row, l)| {
+            let mut current_number: Option<Span> = None;
 
             let v = l.chars()
-                .map(|c| {
+                .enumerate()
+                .map(|(col, c)| {
 
                     if let Some(n) = c.to_digit(10) {
-                        if let Some(cn) = current_number {
-                            current_number = Some(cn * 10 + n as usize);
+                        if let Some(cn) = &mut current_number {
+                            cn.value = cn.value * 10 + n as usize;
+                            cn.end = col;
                         } else {
-                            current_number = Some(n as usize);
+                            current_number = Some(Span {
+                                row,
+                                start: col,
+                                end: col,
+                                value: n as usize,
+                            });
                         }
 
                         Value::Digit {
@@ -76,131 +360,147 @@ fn parse() -> Schematic {
 }
 
 fn part1() {
-    let schematic = parse();
+    let sum = Graph::from(&parse()).part_number_total();
 
-    let mut num_ids = HashSet::new();
+    println!("Part 1: {}", sum);
+}
 
-    for i in 0..schematic.width {
-        for j in 0..schematic.height {
-            if let Value::Symbol(_) = schematic.values[i][j] {
+fn part2() {
+    let graph = Graph::from(&parse());
 
-                if i > 0 && j > 0 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i - 1][j - 1] {
-                        num_ids.insert(num_id);
-                    }
-                }
-                if i > 0 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i - 1][j] {
-                        num_ids.insert(num_id);
-                    }
-                }
-                if i > 0 && j < schematic.height - 1 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i - 1][j + 1] {
-                        num_ids.insert(num_id);
-                    }
-                }
+    let sum = graph.gear_total(&GearRule::from_args());
 
-                if j > 0 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i][j - 1] {
-                        num_ids.insert(num_id);
-                    }
-                }
-                if j < schematic.height - 1 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i][j + 1] {
-                        num_ids.insert(num_id);
-                    }
-                }
+    println!("Part 2: {}", sum);
+}
 
-                if i < schematic.width - 1 && j > 0 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i + 1][j - 1] {
-                        num_ids.insert(num_id);
-                    }
-                }
-                if i < schematic.width - 1 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i + 1][j] {
-                        num_ids.insert(num_id);
-                    }
-                }
-                if i < schematic.width - 1 && j < schematic.height - 1 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i + 1][j + 1] {
-                        num_ids.insert(num_id);
-                    }
-                }
+fn report() {
+    let graph = Graph::from(&parse());
+    let rule = GearRule::from_args();
+
+    let mut symbols: Vec<_> = graph.symbols.iter().map(|&(c, _)| c).collect();
+    symbols.sort();
+    symbols.dedup();
 
+    println!(
+        "{:<8} {:>8} {:>8} {:>12} {:>8} {:>12}",
+        "Symbol", "Nodes", "Numbers", "Number sum", "Gears", "Gear total"
+    );
+
+    for symbol in symbols {
+        let mut nodes = 0;
+        let mut numbers = 0;
+        let mut number_sum = 0;
+        let mut gears = 0;
+        let mut gear_total = 0;
+
+        for (i, _) in graph.symbols.iter().enumerate().filter(|(_, s)| s.0 == symbol) {
+            let adjacent = graph.symbol_numbers(i);
+
+            nodes += 1;
+            numbers += adjacent.len();
+            number_sum += adjacent.iter().sum::<usize>();
+
+            if rule.matches(symbol, &adjacent) {
+                gears += 1;
+                gear_total += rule.value(&adjacent);
             }
         }
-    }
 
-    let sum: usize = num_ids.iter().map(|&id|schematic.numbers[id]).sum();
-
-    println!("Part 1: {}", sum);
+        println!(
+            "{:<8} {:>8} {:>8} {:>12} {:>8} {:>12}",
+            symbol, nodes, numbers, number_sum, gears, gear_total
+        );
+    }
 }
 
-fn part2() {
+fn isolated() {
     let schematic = parse();
 
-    let mut sum = 0;
+    for span in schematic.isolated_numbers() {
+        println!(
+            "row {}, columns {}-{}: {}",
+            span.row, span.start, span.end, span.value
+        );
+    }
+}
 
-    for i in 0..schematic.width {
-        for j in 0..schematic.height {
-            if let Value::Symbol('*') = schematic.values[i][j] {
-                let mut num_ids = HashSet::new();
+fn lookup(row: &str, col: &str) {
+    let schematic = parse();
 
-                if i > 0 && j > 0 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i - 1][j - 1] {
-                        num_ids.insert(num_id);
-                    }
-                }
-                if i > 0 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i - 1][j] {
-                        num_ids.insert(num_id);
-                    }
-                }
-                if i > 0 && j < schematic.height - 1 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i - 1][j + 1] {
-                        num_ids.insert(num_id);
-                    }
-                }
+    let pos = match (row.parse(), col.parse()) {
+        (Ok(row), Ok(col)) => Position { row, col },
+        _ => {
+            println!("Row and column must be numbers, found {} and {}", row, col);
+            return;
+        }
+    };
 
-                if j > 0 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i][j - 1] {
-                        num_ids.insert(num_id);
-                    }
-                }
-                if j < schematic.height - 1 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i][j + 1] {
-                        num_ids.insert(num_id);
-                    }
-                }
+    if schematic.get(pos).is_none() {
+        println!(
+            "({}, {}) is outside the {}x{} schematic",
+            pos.row, pos.col, schematic.height, schematic.width
+        );
+        return;
+    }
 
-                if i < schematic.width - 1 && j > 0 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i + 1][j - 1] {
-                        num_ids.insert(num_id);
-                    }
-                }
-                if i < schematic.width - 1 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i + 1][j] {
-                        num_ids.insert(num_id);
-                    }
-                }
-                if i < schematic.width - 1 && j < schematic.height - 1 {
-                    if let Value::Digit { num_id, .. } = schematic.values[i + 1][j + 1] {
-                        num_ids.insert(num_id);
-                    }
-                }
+    match schematic.span_at(pos) {
+        Some(span) => {
+            let neighbours: Vec<_> = schematic
+                .span_neighbours(&span)
+                .iter()
+                .map(|p| format!("({}, {})", p.row, p.col))
+                .collect();
 
-                if num_ids.len() == 2 {
-                    let num_ids: Vec<_> = num_ids.iter().collect();
-                    sum += schematic.numbers[*num_ids[0]] * schematic.numbers[*num_ids[1]];
-                }
-            }
+            println!(
+                "{} at row {}, columns {}-{}, next to {}",
+                span.value,
+                span.row,
+                span.start,
+                span.end,
+                neighbours.join(" ")
+            );
         }
+        None => println!("No number at ({}, {})", pos.row, pos.col),
     }
-
-    println!("Part 2: {}", sum);
 }
 
 fn main() {
-    part1();
-    part2();
+    match std::env::args().nth(1).as_deref() {
+        Some("dot") => print!("{}", Graph::from(&parse()).to_dot()),
+        Some("report") => report(),
+        Some("isolated") => isolated(),
+        Some("at") => match std::env::args().skip(2).collect::<Vec<_>>().as_slice() {
+            [row, col] => lookup(row, col),
+            _ => println!("Usage: at <row> <col>"),
+        },
+        _ => {
+            part1();
+            part2();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals(input: &str) -> (usize, usize) {
+        let graph = Graph::from(&parse_str(input));
+
+        (graph.part_number_total(), graph.gear_total(&GearRule::default()))
+    }
+
+    #[test]
+    fn wide_grid() {
+        let input = "467..114..*..\n...*......12.\n..35..633....";
+
+        assert_eq!(totals(input), (514, 16345));
+    }
+
+    #[test]
+    fn tall_grid() {
+        let input = "4*\n..\n.3\n3*";
+
+        assert_eq!(totals(input), (10, 9));
+    }
 }
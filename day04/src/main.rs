@@ -1,39 +1,55 @@
+use std::collections::HashSet;
+
 const INPUT: &'static str = include_str!("./input");
 
-#[derive(Debug)]
-struct Card {
-    id: usize,
-    left: Vec<usize>,
-    right: Vec<usize>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Duplicates {
+    // A number repeated on the right only wins once
+    Once,
+    // Every repeat of a winning number on the right wins
+    Each,
+    Reject,
 }
 
-impl Card {
-    fn count_wins(&self) -> usize {
-        let mut count = 0;
+impl Duplicates {
+    fn from_args() -> Duplicates {
+        let mut duplicates = Duplicates::Each;
 
-        for r in &self.right {
-            if self.left.contains(r) {
-                count += 1;
+        for arg in std::env::args().skip(1) {
+            match arg.as_str() {
+                "--duplicates=once" => duplicates = Duplicates::Once,
+                "--duplicates=each" => duplicates = Duplicates::Each,
+                "--duplicates=reject" => duplicates = Duplicates::Reject,
+                _ => {}
             }
         }
 
-        count
+        duplicates
     }
+}
 
-    fn count_rec(list: &Vec<Card>, id: usize) -> usize {
-        let wins = list[id - 1].count_wins();
-
-        if wins == 0 {
-            return 1;
-        }
+#[derive(Debug)]
+struct DuplicateNumber {
+    card: usize,
+    number: usize,
+}
 
-        let mut total = 1;
+impl std::fmt::Display for DuplicateNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "card {}: {} appears more than once", self.card, self.number)
+    }
+}
 
-        for next_id in id + 1..=id + wins {
-            total += Card::count_rec(list, next_id);
-        }
+#[derive(Debug)]
+struct Card {
+    id: usize,
+    left: HashSet<usize>,
+    right: Vec<usize>,
+}
 
-        total
+impl Card {
+    fn count_wins(&self) -> usize {
+        self.right.iter().filter(|r| self.left.contains(r)).count()
     }
 
     fn points(&self) -> usize {
@@ -47,7 +63,7 @@ impl Card {
     }
 }
 
-fn parse() -> Vec<Card> {
+fn parse(duplicates: Duplicates) -> Result<Vec<Card>, DuplicateNumber> {
     INPUT
         .lines()
         .map(|l| {
@@ -55,37 +71,186 @@ fn parse() -> Vec<Card> {
             let id =
                 usize::from_str_radix(id.split_whitespace().skip(1).next().unwrap(), 10).unwrap();
             let (left, right) = cards.split_once("|").unwrap();
-            Card {
-                id,
-                left: left
-                    .split_whitespace()
-                    .filter_map(|n| usize::from_str_radix(n, 10).ok())
-                    .collect(),
-                right: right
-                    .split_whitespace()
-                    .filter_map(|n| usize::from_str_radix(n, 10).ok())
-                    .collect(),
+
+            let left: Vec<_> = left
+                .split_whitespace()
+                .filter_map(|n| usize::from_str_radix(n, 10).ok())
+                .collect();
+            let mut right: Vec<_> = right
+                .split_whitespace()
+                .filter_map(|n| usize::from_str_radix(n, 10).ok())
+                .collect();
+
+            if duplicates == Duplicates::Reject {
+                for side in [&left, &right] {
+                    let mut seen = HashSet::new();
+
+                    if let Some(&number) = side.iter().find(|&&n| !seen.insert(n)) {
+                        return Err(DuplicateNumber { card: id, number });
+                    }
+                }
             }
+
+            if duplicates == Duplicates::Once {
+                let mut seen = HashSet::new();
+                right.retain(|&n| seen.insert(n));
+            }
+
+            Ok(Card {
+                id,
+                left: left.into_iter().collect(),
+                right,
+            })
         })
         .collect()
 }
 
-fn part1() {
-    let total: usize = parse().iter().map(|c| c.points()).sum();
+// Cards whose id is not one more than the card before, as (line, id) pairs
+fn out_of_sequence(cards: &[Card]) -> Vec<(usize, usize)> {
+    cards
+        .iter()
+        .enumerate()
+        .filter(|(i, card)| card.id != i + 1)
+        .map(|(i, card)| (i + 1, card.id))
+        .collect()
+}
+
+#[derive(Debug)]
+struct Explanation {
+    id: usize,
+    matches: usize,
+    points: usize,
+    copies: usize,
+    // (card id, copies won from it)
+    from: Vec<(usize, usize)>,
+    // Copies held of this card and every card before it
+    cumulative: usize,
+}
+
+// How many copies of each card end up being held, including the original, and where they came
+// from. Wins that would reach past the last card are dropped.
+fn simulate(cards: &[Card]) -> Vec<Explanation> {
+    let mut explanations: Vec<_> = cards
+        .iter()
+        .map(|card| Explanation {
+            id: card.id,
+            matches: card.count_wins(),
+            points: card.points(),
+            copies: 1,
+            from: vec![],
+            cumulative: 0,
+        })
+        .collect();
+
+    let mut cumulative = 0;
+
+    for i in 0..explanations.len() {
+        let id = explanations[i].id;
+        let copies = explanations[i].copies;
+        let last = usize::min(i + explanations[i].matches, explanations.len() - 1);
+
+        for won in &mut explanations[i + 1..=last] {
+            won.copies += copies;
+            won.from.push((id, copies));
+        }
+
+        cumulative += copies;
+        explanations[i].cumulative = cumulative;
+    }
+
+    explanations
+}
+
+fn explain(cards: &[Card]) {
+    let explanations = simulate(cards);
+
+    if std::env::args().any(|a| a == "--json") {
+        let rows: Vec<_> = explanations
+            .iter()
+            .map(|e| {
+                let from: Vec<_> = e
+                    .from
+                    .iter()
+                    .map(|(id, copies)| format!("{{\"card\": {}, \"copies\": {}}}", id, copies))
+                    .collect();
+
+                format!(
+                    "  {{\"card\": {}, \"matches\": {}, \"points\": {}, \"copies\": {}, \"from\": [{}], \"cumulative\": {}}}",
+                    e.id,
+                    e.matches,
+                    e.points,
+                    e.copies,
+                    from.join(", "),
+                    e.cumulative
+                )
+            })
+            .collect();
+
+        println!("[\n{}\n]", rows.join(",\n"));
+
+        return;
+    }
+
+    println!("card,matches,points,copies,from,cumulative");
+
+    for e in &explanations {
+        let from: Vec<_> = e
+            .from
+            .iter()
+            .map(|(id, copies)| format!("{}:{}", id, copies))
+            .collect();
+
+        println!(
+            "{},{},{},{},{},{}",
+            e.id,
+            e.matches,
+            e.points,
+            e.copies,
+            from.join(";"),
+            e.cumulative
+        );
+    }
+}
+
+fn validate(cards: &[Card]) {
+    let out_of_sequence = out_of_sequence(cards);
+
+    if out_of_sequence.is_empty() {
+        println!("All {} cards are in sequence", cards.len());
+    }
+
+    for (line, id) in out_of_sequence {
+        println!("line {}: expected card {}, found card {}", line, line, id);
+    }
+}
+
+fn part1(cards: &[Card]) {
+    let total: usize = cards.iter().map(|c| c.points()).sum();
 
     println!("Part 1: {}", total);
 }
 
-fn part2() {
-    let cards = parse();
-    let total: usize = (1..=cards.len())
-        .map(|i| Card::count_rec(&parse(), i))
-        .sum();
+fn part2(cards: &[Card]) {
+    let total: usize = simulate(cards).iter().map(|e| e.copies).sum();
 
     println!("Part 2: {}", total);
 }
 
 fn main() {
-    part1();
-    part2();
+    let cards = match parse(Duplicates::from_args()) {
+        Ok(cards) => cards,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    match std::env::args().nth(1).as_deref() {
+        Some("validate") => validate(&cards),
+        Some("explain") => explain(&cards),
+        _ => {
+            part1(&cards);
+            part2(&cards);
+        }
+    }
 }
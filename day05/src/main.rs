@@ -1,61 +1,128 @@
+use std::collections::{HashMap, VecDeque};
+
 const INPUT: &'static str = include_str!("./input");
 
-#[derive(Debug, Clone)]
+// A set of half-open ranges, kept sorted with no overlapping or touching ranges
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 struct RangeSet {
     ranges: Vec<(usize, usize)>
 }
+
+impl From<(usize, usize)> for RangeSet {
+    fn from(range: (usize, usize)) -> Self {
+        RangeSet::from(vec![range])
+    }
+}
+
+impl From<Vec<(usize, usize)>> for RangeSet {
+    fn from(mut ranges: Vec<(usize, usize)>) -> Self {
+        ranges.retain(|r| r.0 < r.1);
+        ranges.sort();
+
+        let mut merged: Vec<(usize, usize)> = vec![];
+
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.0 <= last.1 => last.1 = usize::max(last.1, range.1),
+                _ => merged.push(range),
+            }
+        }
+
+        RangeSet { ranges: merged }
+    }
+}
+
+impl std::fmt::Display for RangeSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ranges: Vec<_> = self.iter().map(|(start, end)| format!("{}..{}", start, end)).collect();
+
+        write!(f, "{{{}}}", ranges.join(", "))
+    }
+}
+
 impl RangeSet {
-    fn min(&self) -> usize {
-        self.ranges.iter().map(|r|r.0).min().unwrap()
+    fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.ranges.iter().cloned()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    fn min(&self) -> Option<usize> {
+        self.ranges.first().map(|r| r.0)
+    }
+
+    fn max(&self) -> Option<usize> {
+        self.ranges.last().map(|r| r.1 - 1)
     }
 
     fn shift(&mut self, positive: usize, negative: usize) {
         for range in &mut self.ranges {
-            range.0 += positive;
-            range.0 -= negative;
-
-            range.1 += positive;
-            range.1 -= negative;
+            if positive >= negative {
+                range.0 += positive - negative;
+                range.1 += positive - negative;
+            } else {
+                range.0 -= negative - positive;
+                range.1 -= negative - positive;
+            }
         }
     }
 
-    fn combine(&mut self, other: &mut RangeSet) {
-        self.ranges.append(&mut other.ranges);
+    fn union(&self, other: &RangeSet) -> RangeSet {
+        RangeSet::from(self.iter().chain(other.iter()).collect::<Vec<_>>())
     }
 
-    fn overlap(&self, start: usize, end: usize) -> (RangeSet, RangeSet)  {
-        let mut overlaps = RangeSet {ranges: vec![]};
-        let mut remaining = RangeSet {ranges: vec![]};
+    fn intersection(&self, other: &RangeSet) -> RangeSet {
+        let mut ranges = vec![];
+        let (mut i, mut j) = (0, 0);
 
-        for &range in &self.ranges {
-            if range.1 <= start {
-                remaining.ranges.push(range);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (self.ranges[i], other.ranges[j]);
+
+            let start = usize::max(a.0, b.0);
+            let end = usize::min(a.1, b.1);
+            if start < end {
+                ranges.push((start, end));
             }
-            else if range.0 >= end {
-                remaining.ranges.push(range);
+
+            if a.1 < b.1 {
+                i += 1;
+            } else {
+                j += 1;
             }
-            else if range.0 >= start && range.1 <= end {
-                overlaps.ranges.push(range);
-            } 
-            else if range.0 < start && range.1 > end {
-                overlaps.ranges.push((start, end));
-                remaining.ranges.push((range.0, start));
-                remaining.ranges.push((end, range.1));
+        }
+
+        RangeSet { ranges }
+    }
+
+    fn difference(&self, other: &RangeSet) -> RangeSet {
+        let mut ranges = vec![];
+        let mut j = 0;
+
+        for (mut start, end) in self.iter() {
+            while j < other.ranges.len() && other.ranges[j].1 <= start {
+                j += 1;
             }
-            else if range.0 >= start  && range.1 > end {
-                overlaps.ranges.push((range.0, end));
-                remaining.ranges.push((end, range.1));
 
+            let mut k = j;
+            while k < other.ranges.len() && other.ranges[k].0 < end {
+                let (other_start, other_end) = other.ranges[k];
+
+                if other_start > start {
+                    ranges.push((start, other_start));
+                }
+                start = usize::max(start, other_end);
+
+                k += 1;
+            }
 
-            } else if range.0 < start  && range.1 <= end {
-                overlaps.ranges.push((start, range.1));
-                remaining.ranges.push((range.0, start));
-            } else  {
-                panic!()
+            if start < end {
+                ranges.push((start, end));
             }
         }
 
-        (overlaps, remaining)
+        RangeSet { ranges }
     }
 }
 
@@ -64,6 +131,8 @@ struct MapRange {
     destination_start: usize,
     source_start: usize,
     length: usize,
+    // Where the range was read from, if it came from the input
+    line: Option<usize>,
 }
 
 impl From<&str> for MapRange {
@@ -76,10 +145,17 @@ impl From<&str> for MapRange {
             destination_start: iter.next().unwrap(),
             source_start: iter.next().unwrap(),
             length: iter.next().unwrap(),
+            line: None,
         }
     }
 }
 
+impl std::fmt::Display for MapRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.destination_start, self.source_start, self.length)
+    }
+}
+
 impl MapRange {
     fn map(&self, source: usize) -> Option<usize> {
         if source >= self.source_start && source - self.source_start < self.length {
@@ -89,103 +165,405 @@ impl MapRange {
         None
     }
 
-    fn map_range(&self, source: RangeSet) -> (RangeSet, RangeSet) {
-        let (mut overlap, remaining) = source.overlap(self.source_start, self.source_start + self.length);
+    fn source(&self) -> RangeSet {
+        RangeSet::from((self.source_start, self.source_start + self.length))
+    }
+
+    // The part of `source` this range moves, already moved, and the part it leaves alone
+    fn map_range(&self, source: &RangeSet) -> (RangeSet, RangeSet) {
+        let mut overlap = source.intersection(&self.source());
         overlap.shift(self.destination_start, self.source_start);
 
-        (overlap, remaining)
+        (overlap, source.difference(&self.source()))
     }
 }
 
 #[derive(Debug)]
 struct Map {
+    source: String,
+    destination: String,
     ranges: Vec<MapRange>,
 }
 
 impl From<&str> for Map {
     fn from(value: &str) -> Self {
+        let (header, ranges) = value.split_once(" map:\n").unwrap();
+        let (source, destination) = header.split_once("-to-").unwrap();
+
         Self {
-            ranges: value.lines().map(|l| l.into()).collect(),
+            source: source.to_string(),
+            destination: destination.to_string(),
+            // Lines are counted from the header, which is line 1
+            ranges: ranges
+                .lines()
+                .enumerate()
+                .map(|(i, l)| MapRange { line: Some(i + 2), ..l.into() })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Issue {
+    // Two ranges claim the same sources, so the one listed first wins
+    Overlap { map: String, lines: (usize, usize), sources: (usize, usize) },
+    // Sources between two ranges that no range covers, so they map to themselves
+    Gap { map: String, lines: (usize, usize), sources: (usize, usize) },
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::Overlap { map, lines, sources } => write!(
+                f,
+                "{}: lines {} and {} overlap on sources {}..{}",
+                map, lines.0, lines.1, sources.0, sources.1
+            ),
+            Issue::Gap { map, lines, sources } => write!(
+                f,
+                "{}: sources {}..{} between lines {} and {} are not mapped",
+                map, sources.0, sources.1, lines.0, lines.1
+            ),
         }
     }
 }
 
 impl Map {
+    fn name(&self) -> String {
+        format!("{}-to-{} map", self.source, self.destination)
+    }
+
+    fn validate(&self) -> Vec<Issue> {
+        let mut issues = vec![];
+
+        let mut ranges = self.ranges.clone();
+        ranges.sort_by_key(|r| r.source_start);
+
+        for (i, a) in ranges.iter().enumerate() {
+            for b in &ranges[i + 1..] {
+                let start = b.source_start;
+                let end = usize::min(a.source_start + a.length, b.source_start + b.length);
+
+                if start < end {
+                    issues.push(Issue::Overlap {
+                        map: self.name(),
+                        lines: (a.line.unwrap_or(0), b.line.unwrap_or(0)),
+                        sources: (start, end),
+                    });
+                }
+            }
+        }
+
+        // Walk the ranges in order, remembering the one that reaches furthest so far
+        let mut furthest: Option<&MapRange> = None;
+        for range in &ranges {
+            if let Some(previous) = furthest {
+                let end = previous.source_start + previous.length;
+
+                if end < range.source_start {
+                    issues.push(Issue::Gap {
+                        map: self.name(),
+                        lines: (previous.line.unwrap_or(0), range.line.unwrap_or(0)),
+                        sources: (end, range.source_start),
+                    });
+                }
+
+                if end >= range.source_start + range.length {
+                    continue;
+                }
+            }
+
+            furthest = Some(range);
+        }
+
+        issues
+    }
+
     fn map(&self, source: usize) -> usize {
+        match self.find(source) {
+            Some(range) => range.map(source).unwrap(),
+            None => source,
+        }
+    }
+
+    // The range that maps `source`, if any
+    fn find(&self, source: usize) -> Option<&MapRange> {
+        self.ranges.iter().find(|range| range.map(source).is_some())
+    }
+
+    // (source start, source end, destination start) for every stretch of sources, sorted and
+    // covering all of them, with the gaps between ranges mapped to themselves
+    fn pieces(&self) -> Vec<(usize, usize, usize)> {
+        let mut remaining = RangeSet::from((0, usize::MAX));
+        let mut pieces = vec![];
+
         for range in &self.ranges {
-            if let Some(destination) = range.map(source) {
-                return destination;
+            for (start, end) in remaining.intersection(&range.source()).iter() {
+                pieces.push((start, end, start - range.source_start + range.destination_start));
+            }
+
+            remaining = remaining.difference(&range.source());
+        }
+
+        for (start, end) in remaining.iter() {
+            pieces.push((start, end, start));
+        }
+
+        pieces.sort();
+
+        pieces
+    }
+
+    // A map equivalent to applying this map and then `next`
+    fn compose(&self, next: &Map) -> Map {
+        let next_pieces = next.pieces();
+        let mut ranges = vec![];
+
+        for (start, end, destination) in self.pieces() {
+            let destination_end = destination + (end - start);
+
+            for &(next_start, next_end, next_destination) in &next_pieces {
+                let low = usize::max(destination, next_start);
+                let high = usize::min(destination_end, next_end);
+
+                if low >= high {
+                    continue;
+                }
+
+                let range = MapRange {
+                    destination_start: low - next_start + next_destination,
+                    source_start: low - destination + start,
+                    length: high - low,
+                    line: None,
+                };
+
+                if range.destination_start != range.source_start {
+                    ranges.push(range);
+                }
             }
         }
 
-        return source;
+        Map {
+            source: self.source.clone(),
+            destination: next.destination.clone(),
+            ranges,
+        }
     }
 
-    fn map_range(&self, source: RangeSet) -> RangeSet {
+    // Every source that lands in `destination`
+    fn inverse(&self, destination: &RangeSet) -> RangeSet {
+        let mut sources = RangeSet::default();
+
+        for (start, end, piece_destination) in self.pieces() {
+            let image = RangeSet::from((piece_destination, piece_destination + (end - start)));
+
+            let mut overlap = destination.intersection(&image);
+            overlap.shift(start, piece_destination);
+            sources = sources.union(&overlap);
+        }
+
+        sources
+    }
+
+    // The lowest destination of any source in [start, end)
+    fn min_over(&self, start: usize, end: usize) -> Option<usize> {
+        self.pieces()
+            .into_iter()
+            .filter(|&(piece_start, piece_end, _)| piece_start < end && piece_end > start)
+            .map(|(piece_start, _, destination)| usize::max(start, piece_start) - piece_start + destination)
+            .min()
+    }
+
+    // What each range moved, after moving it, and the part of `source` no range moved
+    fn map_fragments(&self, source: RangeSet) -> (Vec<(MapRange, RangeSet)>, RangeSet) {
         let mut remaining = source;
-        let mut mapped = RangeSet{ ranges: vec![] };
+        let mut fragments = vec![];
 
         for range in &self.ranges {
-            let mut result = range.map_range(remaining);
-            remaining = result.1;
-            mapped.combine(&mut result.0);
+            let (moved, rest) = range.map_range(&remaining);
+            remaining = rest;
+
+            if !moved.is_empty() {
+                fragments.push((*range, moved));
+            }
         }
 
-        mapped.combine(&mut remaining);
+        (fragments, remaining)
+    }
 
-        mapped
+    fn map_range(&self, source: RangeSet) -> RangeSet {
+        let (fragments, remaining) = self.map_fragments(source);
+
+        fragments.iter().fold(remaining, |mapped, (_, moved)| mapped.union(moved))
     }
 }
 
 #[derive(Debug)]
 struct Almanac {
     seeds: Vec<usize>,
-    seed_to_soil: Map,
-    soil_to_fertilizer: Map,
-    fertilizer_to_water: Map,
-    water_to_light: Map,
-    light_to_temperature: Map,
-    temperature_to_humidity: Map,
-    humidity_to_location: Map,
+    maps: Vec<Map>,
 }
 
 impl From<&str> for Almanac {
     fn from(value: &str) -> Self {
-        let (seeds, value) = value.split_once("\nseed-to-soil map:\n").unwrap();
-        let (seed_to_soil, value) = value.split_once("\nsoil-to-fertilizer map:\n").unwrap();
-        let (soil_to_fertilizer, value) = value.split_once("\nfertilizer-to-water map:\n").unwrap();
-        let (fertilizer_to_water, value) = value.split_once("\nwater-to-light map:\n").unwrap();
-        let (water_to_light, value) = value.split_once("\nlight-to-temperature map:\n").unwrap();
-        let (light_to_temperature, value) = value
-            .split_once("\ntemperature-to-humidity map:\n")
-            .unwrap();
-        let (temperature_to_humidity, humidity_to_location) =
-            value.split_once("\nhumidity-to-location map:\n").unwrap();
+        let mut sections = value.split("\n\n");
+
+        let seeds = sections.next().unwrap();
+        let mut offset = seeds.len() + 2;
+
+        let mut maps = vec![];
+        for section in sections {
+            let leading = section.len() - section.trim_start().len();
+            let header_line = value[..offset + leading].matches('\n').count() + 1;
+            offset += section.len() + 2;
+
+            let mut map: Map = section.trim().into();
+            for range in &mut map.ranges {
+                range.line = range.line.map(|l| l + header_line - 1);
+            }
+
+            maps.push(map);
+        }
 
         Almanac {
             seeds: seeds.split_once(":").unwrap().1.split_whitespace().map(|n|usize::from_str_radix(n, 10).unwrap()).collect(),
-            seed_to_soil: seed_to_soil.into(),
-            soil_to_fertilizer: soil_to_fertilizer.into(),
-            fertilizer_to_water: fertilizer_to_water.into(),
-            water_to_light: water_to_light.into(),
-            light_to_temperature: light_to_temperature.into(),
-            temperature_to_humidity: temperature_to_humidity.into(),
-            humidity_to_location: humidity_to_location.into(),
+            maps,
         }
     }
 }
 
+impl Almanac {
+    fn validate(&self) -> Vec<Issue> {
+        self.maps.iter().flat_map(|m| m.validate()).collect()
+    }
+
+    // The maps to apply, in order, to get from one category to another
+    fn path(&self, from: &str, to: &str) -> Option<Vec<&Map>> {
+        let mut previous: HashMap<&str, &Map> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut path = vec![];
+                let mut category = to;
+
+                while category != from {
+                    let map = previous[category];
+                    path.push(map);
+                    category = &map.source;
+                }
+
+                path.reverse();
+
+                return Some(path);
+            }
+
+            for map in self.maps.iter().filter(|m| m.source == category) {
+                if map.destination != from && !previous.contains_key(map.destination.as_str()) {
+                    previous.insert(&map.destination, map);
+                    queue.push_back(&map.destination);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn map(&self, from: &str, to: &str, source: usize) -> Option<usize> {
+        let path = self.path(from, to)?;
+
+        Some(path.iter().fold(source, |n, map| map.map(n)))
+    }
+
+    // All the maps between two categories collapsed into one
+    fn compose(&self, from: &str, to: &str) -> Option<Map> {
+        let identity = Map {
+            source: from.to_string(),
+            destination: from.to_string(),
+            ranges: vec![],
+        };
+
+        let path = self.path(from, to)?;
+
+        Some(path.iter().fold(identity, |composed, map| composed.compose(map)))
+    }
+
+    // The values of `from` that end up in `destination`, a set of `to` values
+    fn inverse(&self, from: &str, to: &str, destination: RangeSet) -> Option<RangeSet> {
+        let path = self.path(from, to)?;
+
+        Some(path.iter().rev().fold(destination, |range, map| map.inverse(&range)))
+    }
+
+    fn map_range(&self, from: &str, to: &str, source: RangeSet) -> Option<RangeSet> {
+        let path = self.path(from, to)?;
+
+        Some(path.iter().fold(source, |range, map| map.map_range(range)))
+    }
+}
+
+#[derive(Debug)]
+struct Step<'a> {
+    category: &'a str,
+    value: usize,
+    // The range of the map into this category that moved the value
+    range: Option<MapRange>,
+}
+
+#[derive(Debug)]
+struct RangeStep<'a> {
+    category: &'a str,
+    ranges: RangeSet,
+    // What each range of the map into this category moved, after moving it
+    moved: Vec<(MapRange, RangeSet)>,
+}
+
+impl Almanac {
+    fn trace<'a>(&'a self, from: &'a str, to: &str, value: usize) -> Option<Vec<Step<'a>>> {
+        let mut steps = vec![Step { category: from, value, range: None }];
+
+        for map in self.path(from, to)? {
+            let value = steps.last().unwrap().value;
+
+            steps.push(Step {
+                category: &map.destination,
+                value: map.map(value),
+                range: map.find(value).cloned(),
+            });
+        }
+
+        Some(steps)
+    }
+
+    fn trace_range<'a>(
+        &'a self,
+        from: &'a str,
+        to: &str,
+        source: RangeSet,
+    ) -> Option<Vec<RangeStep<'a>>> {
+        let mut steps = vec![RangeStep { category: from, ranges: source, moved: vec![] }];
+
+        for map in self.path(from, to)? {
+            let (moved, remaining) = map.map_fragments(steps.last().unwrap().ranges.clone());
+            let ranges = moved.iter().fold(remaining, |ranges, (_, m)| ranges.union(m));
+
+            steps.push(RangeStep {
+                category: &map.destination,
+                ranges,
+                moved,
+            });
+        }
+
+        Some(steps)
+    }
+}
+
 fn part1() {
     let almanac = Almanac::from(INPUT);
+    let seed_to_location = almanac.compose("seed", "location").unwrap();
 
-    let min = almanac.seeds.iter().cloned()
-    .map(|n| almanac.seed_to_soil.map(n))
-    .map(|n| almanac.soil_to_fertilizer.map(n))
-    .map(|n| almanac.fertilizer_to_water.map(n))
-    .map(|n| almanac.water_to_light.map(n))
-    .map(|n| almanac.light_to_temperature.map(n))
-    .map(|n| almanac.temperature_to_humidity.map(n))
-    .map(|n| almanac.humidity_to_location.map(n))
+    let min = almanac.seeds.iter()
+    .map(|&n| seed_to_location.map(n))
     .min().unwrap();
 
     println!("Part 1: {}", min);
@@ -193,29 +571,415 @@ fn part1() {
 
 fn part2() {
     let almanac = Almanac::from(INPUT);
+    let seed_to_location = almanac.compose("seed", "location").unwrap();
 
-    let mut min: usize = 999999999999999999;
+    let min = almanac.seeds.chunks(2)
+    .filter_map(|seeds| seed_to_location.min_over(seeds[0], seeds[0] + seeds[1]))
+    .min().unwrap();
 
-    for seeds in almanac.seeds.chunks(2) {
-        let mut range = RangeSet{ranges: vec![(seeds[0], seeds[0] + seeds[1])]};
+    println!("Part 2: {}", min);
+}
 
-        range = almanac.seed_to_soil.map_range(range);
-        range = almanac.soil_to_fertilizer.map_range(range);
-        range = almanac.fertilizer_to_water.map_range(range);
-        range = almanac.water_to_light.map_range(range);
-        range = almanac.light_to_temperature.map_range(range);
-        range = almanac.temperature_to_humidity.map_range(range);
-        range = almanac.humidity_to_location.map_range(range);
+// Part 2 worked backwards: the lowest location whose seeds include one from the seed ranges
+fn check() {
+    let almanac = Almanac::from(INPUT);
 
-        let range_min = range.min();
+    let seeds = RangeSet::from(almanac.seeds.chunks(2).map(|s| (s[0], s[0] + s[1])).collect::<Vec<_>>());
+    let reachable = |end: usize| {
+        let sources = almanac.inverse("seed", "location", RangeSet::from((0, end))).unwrap();
+        !sources.intersection(&seeds).is_empty()
+    };
+
+    // Find the smallest `end` such that some seed reaches a location below it
+    let (mut low, mut high) = (0, usize::MAX);
+    while low + 1 < high {
+        let mid = low + (high - low) / 2;
+
+        if reachable(mid) {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+
+    println!("Part 2 (inverse): {}", high - 1);
+}
 
-        min = min.min(range_min);
+fn sources(from: &str, to: &str, start: usize, length: usize) {
+    let almanac = Almanac::from(INPUT);
+    let range = RangeSet::from((start, start + length));
+
+    match almanac.inverse(from, to, range) {
+        Some(result) => println!("{} {}..{} <- {} {}", to, start, start + length, from, result),
+        None => println!("No chain of maps from {} to {}", from, to),
     }
+}
 
-    println!("Part 2: {}", min);
+fn trace(to: &str, value: usize) {
+    let almanac = Almanac::from(INPUT);
+
+    match almanac.trace("seed", to, value) {
+        Some(steps) => {
+            for step in steps {
+                match step.range {
+                    Some(range) => println!("{} {} (range {})", step.category, step.value, range),
+                    None => println!("{} {}", step.category, step.value),
+                }
+            }
+        }
+        None => println!("No chain of maps from seed to {}", to),
+    }
+}
+
+fn trace_range(to: &str, start: usize, length: usize) {
+    let almanac = Almanac::from(INPUT);
+
+    let range = RangeSet::from((start, start + length));
+
+    match almanac.trace_range("seed", to, range) {
+        Some(steps) => {
+            for step in steps {
+                println!("{} {}", step.category, step.ranges);
+
+                for (range, moved) in step.moved {
+                    println!("    range {} moved {}", range, moved);
+                }
+            }
+        }
+        None => println!("No chain of maps from seed to {}", to),
+    }
+}
+
+fn validate() {
+    let issues = Almanac::from(INPUT).validate();
+
+    if issues.is_empty() {
+        println!("No overlapping ranges or gaps");
+    }
+
+    for issue in issues {
+        println!("{}", issue);
+    }
+}
+
+fn lookup(from: &str, to: &str, value: usize) {
+    let almanac = Almanac::from(INPUT);
+
+    match almanac.map(from, to, value) {
+        Some(result) => println!("{} {} -> {} {}", from, value, to, result),
+        None => println!("No chain of maps from {} to {}", from, to),
+    }
+}
+
+fn lookup_range(from: &str, to: &str, start: usize, length: usize) {
+    let almanac = Almanac::from(INPUT);
+    let range = RangeSet::from((start, start + length));
+
+    match almanac.map_range(from, to, range) {
+        Some(result) => match (result.min(), result.max()) {
+            (Some(min), Some(max)) => println!(
+                "{} {}..{} -> {} {}, lowest {}, highest {}",
+                from,
+                start,
+                start + length,
+                to,
+                result,
+                min,
+                max
+            ),
+            _ => println!("{} {}..{} is empty", from, start, start + length),
+        },
+        None => println!("No chain of maps from {} to {}", from, to),
+    }
 }
 
 fn main() {
+    let args: Vec<_> = std::env::args().skip(1).filter(|a| !a.starts_with("--")).collect();
+
+    // Strict mode refuses almanacs where the order of ranges decides the answer
+    if std::env::args().any(|a| a == "--strict") {
+        let overlaps: Vec<_> = Almanac::from(INPUT)
+            .validate()
+            .into_iter()
+            .filter(|i| matches!(i, Issue::Overlap { .. }))
+            .collect();
+
+        if !overlaps.is_empty() {
+            for overlap in overlaps {
+                println!("{}", overlap);
+            }
+            return;
+        }
+    }
+
+    if args.len() == 1 && args[0] == "validate" {
+        validate();
+        return;
+    }
+
+    if args.len() == 4 && args[0] == "lookup" {
+        lookup(&args[1], &args[2], args[3].parse().unwrap());
+        return;
+    }
+
+    // Tracing stops at the location unless another category is given with --to=
+    let to = std::env::args()
+        .find_map(|a| a.strip_prefix("--to=").map(String::from))
+        .unwrap_or("location".to_string());
+
+    if args.len() == 2 && args[0] == "trace" {
+        trace(&to, args[1].parse().unwrap());
+        return;
+    }
+
+    if args.len() == 3 && args[0] == "trace" {
+        trace_range(&to, args[1].parse().unwrap(), args[2].parse().unwrap());
+        return;
+    }
+
+    if args.len() == 1 && args[0] == "check" {
+        check();
+        return;
+    }
+
+    if args.len() == 5 && args[0] == "sources" {
+        sources(&args[1], &args[2], args[3].parse().unwrap(), args[4].parse().unwrap());
+        return;
+    }
+
+    if args.len() == 5 && args[0] == "lookup" {
+        lookup_range(&args[1], &args[2], args[3].parse().unwrap(), args[4].parse().unwrap());
+        return;
+    }
+
     part1();
     part2();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // xorshift, so the random cases are the same on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+    }
+
+    fn points(set: &RangeSet) -> HashSet<usize> {
+        set.iter().flat_map(|(start, end)| start..end).collect()
+    }
+
+    fn from_points(points: &HashSet<usize>) -> RangeSet {
+        RangeSet::from(points.iter().map(|&n| (n, n + 1)).collect::<Vec<_>>())
+    }
+
+    fn assert_normalised(set: &RangeSet) {
+        for &(start, end) in &set.ranges {
+            assert!(start < end, "{} has an empty range", set);
+        }
+
+        for pair in set.ranges.windows(2) {
+            assert!(pair[0].1 < pair[1].0, "{} has touching or unsorted ranges", set);
+        }
+    }
+
+    fn random_set(rng: &mut Rng, universe: usize) -> RangeSet {
+        let ranges = (0..rng.below(5))
+            .map(|_| {
+                let start = rng.below(universe);
+                (start, usize::min(universe, start + rng.below(10)))
+            })
+            .collect::<Vec<_>>();
+
+        RangeSet::from(ranges)
+    }
+
+    fn check_operations(a: &RangeSet, b: &RangeSet) {
+        let (pa, pb) = (points(a), points(b));
+
+        let union = a.union(b);
+        assert_normalised(&union);
+        assert_eq!(points(&union), &pa | &pb, "{} | {}", a, b);
+
+        let intersection = a.intersection(b);
+        assert_normalised(&intersection);
+        assert_eq!(points(&intersection), &pa & &pb, "{} & {}", a, b);
+
+        let difference = a.difference(b);
+        assert_normalised(&difference);
+        assert_eq!(points(&difference), &pa - &pb, "{} - {}", a, b);
+    }
+
+    fn check_shift(set: &RangeSet, positive: usize, negative: usize) {
+        let mut shifted = set.clone();
+        shifted.shift(positive, negative);
+
+        assert_normalised(&shifted);
+        assert_eq!(
+            points(&shifted),
+            points(set).iter().map(|n| n + positive - negative).collect(),
+            "{} shifted by +{} -{}",
+            set,
+            positive,
+            negative
+        );
+    }
+
+    #[test]
+    fn range_set_exhaustive() {
+        // Every subset of 0..8, from each of its points
+        let sets: Vec<_> = (0..1 << 8)
+            .map(|mask: usize| from_points(&(0..8).filter(|n| mask & 1 << n != 0).collect()))
+            .collect();
+
+        for a in &sets {
+            assert_normalised(a);
+
+            for b in &sets {
+                check_operations(a, b);
+            }
+
+            for positive in 0..4 {
+                for negative in 0..=a.min().unwrap_or(0) {
+                    check_shift(a, positive, negative);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn range_set_random() {
+        let mut rng = Rng(0x5eed);
+
+        for _ in 0..2000 {
+            let a = random_set(&mut rng, 60);
+            let b = random_set(&mut rng, 60);
+
+            assert_normalised(&a);
+            assert_eq!(a, from_points(&points(&a)));
+            check_operations(&a, &b);
+
+            let positive = rng.below(20);
+            let negative = rng.below(a.min().unwrap_or(0) + 1);
+            check_shift(&a, positive, negative);
+        }
+    }
+
+    // Ranges are allowed to overlap, in which case the one listed first wins
+    fn random_map(rng: &mut Rng, source: &str, destination: &str) -> Map {
+        let ranges = (0..1 + rng.below(5))
+            .map(|_| MapRange {
+                destination_start: rng.below(40),
+                source_start: rng.below(40),
+                length: 1 + rng.below(10),
+                line: None,
+            })
+            .collect();
+
+        Map {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            ranges,
+        }
+    }
+
+    #[test]
+    fn map_agrees_with_map_range_compose_and_inverse() {
+        let mut rng = Rng(0xa1a1);
+
+        for _ in 0..500 {
+            let map = random_map(&mut rng, "a", "b");
+            let next = random_map(&mut rng, "b", "c");
+            let composed = map.compose(&next);
+
+            for n in 0..100 {
+                assert_eq!(composed.map(n), next.map(map.map(n)), "{} through {:?}", n, composed);
+            }
+
+            let set = random_set(&mut rng, 60);
+            let mapped: HashSet<_> = points(&set).iter().map(|&n| map.map(n)).collect();
+            assert_eq!(points(&map.map_range(set.clone())), mapped, "{} through {:?}", set, map);
+
+            // Every source that can land below 60 is below 60 itself
+            let sources: HashSet<_> = (0..60)
+                .filter(|&n| points(&set).contains(&map.map(n)))
+                .collect();
+            assert_eq!(points(&map.inverse(&set)), sources, "{} back through {:?}", set, map);
+
+            let (start, end) = (rng.below(50), 50 + rng.below(10));
+            assert_eq!(map.min_over(start, end), (start..end).map(|n| map.map(n)).min());
+        }
+    }
+
+    fn random_almanac(rng: &mut Rng) -> String {
+        let categories = [
+            "seed",
+            "soil",
+            "fertilizer",
+            "water",
+            "light",
+            "temperature",
+            "humidity",
+            "location",
+        ];
+
+        let seeds: Vec<_> = (0..8)
+            .map(|i| if i % 2 == 0 { rng.below(200) } else { 1 + rng.below(60) }.to_string())
+            .collect();
+        let mut sections = vec![format!("seeds: {}", seeds.join(" "))];
+
+        for pair in categories.windows(2) {
+            let lines: Vec<_> = (0..1 + rng.below(4))
+                .map(|_| format!("{} {} {}", rng.below(300), rng.below(300), 1 + rng.below(60)))
+                .collect();
+
+            sections.push(format!("{}-to-{} map:\n{}", pair[0], pair[1], lines.join("\n")));
+        }
+
+        sections.join("\n\n")
+    }
+
+    #[test]
+    fn random_almanacs() {
+        let mut rng = Rng(0xa17a);
+
+        for _ in 0..40 {
+            let input = random_almanac(&mut rng);
+            let almanac = Almanac::from(input.as_str());
+            let seed_to_location = almanac.compose("seed", "location").unwrap();
+
+            let chain = |seed: usize| almanac.maps.iter().fold(seed, |n, map| map.map(n));
+
+            let part1 = almanac.seeds.iter().map(|&n| seed_to_location.map(n)).min();
+            assert_eq!(part1, almanac.seeds.iter().map(|&n| chain(n)).min(), "{}", input);
+
+            let part2 = almanac
+                .seeds
+                .chunks(2)
+                .filter_map(|s| seed_to_location.min_over(s[0], s[0] + s[1]))
+                .min();
+            let expected = almanac.seeds.chunks(2).flat_map(|s| s[0]..s[0] + s[1]).map(chain).min();
+            assert_eq!(part2, expected, "{}", input);
+
+            for seeds in almanac.seeds.chunks(2) {
+                let range = RangeSet::from((seeds[0], seeds[0] + seeds[1]));
+                let locations: HashSet<_> = points(&range).iter().map(|&n| chain(n)).collect();
+                let mapped = almanac.map_range("seed", "location", range).unwrap();
+
+                assert_eq!(points(&mapped), locations, "{}", input);
+            }
+        }
+    }
+}
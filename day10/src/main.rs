@@ -1,12 +1,73 @@
 const INPUT: &'static str = include_str!("./input");
 
 use std::collections::HashMap;
+use std::io::BufRead;
 
 fn main() {
+    if std::env::args().nth(1).as_deref() == Some("explore") {
+        // Explore the puzzle input unless a file is given
+        match std::env::args().nth(2) {
+            Some(path) => match std::fs::read_to_string(&path) {
+                Ok(input) => explore(&input),
+                Err(e) => println!("Could not read {}: {}", path, e),
+            },
+            None => explore(INPUT),
+        }
+        return;
+    }
+
     part1();
     part2();
 }
 
+fn explore(input: &str) {
+    let map: Map = input.into();
+    let pipes = map.loop_points();
+
+    println!("Enter a cursor position as `x y` (1-based, from the top left), or q to quit");
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line.unwrap();
+
+        if line.trim() == "q" {
+            break;
+        }
+
+        let cursor = line
+            .split_once(' ')
+            .and_then(|(x, y)| Some((x.trim().parse::<usize>().ok()?, y.trim().parse::<usize>().ok()?)))
+            .filter(|&(x, y)| x >= 1 && x <= map.width - 2 && y >= 1 && y <= map.height - 2);
+
+        let Some((cursor_x, cursor_y)) = cursor else {
+            println!("Not a position on the map: {}", line.trim());
+            continue;
+        };
+
+        for (y, row) in input.lines().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if (x + 1, y + 1) == (cursor_x, cursor_y) {
+                    print!("@");
+                } else if pipes.contains(&map.position(x + 1, y + 1)) {
+                    print!("{}", c);
+                } else {
+                    print!(".");
+                }
+            }
+            println!();
+        }
+
+        let pos = map.position(cursor_x, cursor_y);
+
+        if pipes.contains(&pos) {
+            println!("({}, {}) is on the loop", cursor_x, cursor_y);
+        } else if Map::enclosed(pos, &pipes) {
+            println!("({}, {}) is enclosed", cursor_x, cursor_y);
+        } else {
+            println!("({}, {}) is not enclosed", cursor_x, cursor_y);
+        }
+    }
+}
+
 fn part1() {
     let map: Map = INPUT.into();
     let (loop_start, loop_length) = map.find_loop();
@@ -115,9 +176,7 @@ impl Map {
  
     fn count_enclosed(&self) -> usize {
 
-        let (loop_start, loop_length) = self.find_loop();
-
-        let pipes: Vec<_> = self.all_loop_points(loop_start, loop_length).into_iter().collect();
+        let pipes = self.loop_points();
 
         let mut total = 0;
 
@@ -134,6 +193,16 @@ impl Map {
         total
     }
 
+    fn loop_points(&self) -> Vec<(usize, usize)> {
+        let (loop_start, loop_length) = self.find_loop();
+
+        self.all_loop_points(loop_start, loop_length)
+    }
+
+    fn position(&self, x: usize, y: usize) -> (usize, usize) {
+        (x, self.height - 1 - y)
+    }
+
     fn all_loop_points(&self, mut next: (usize, usize), length: usize) -> Vec<(usize, usize)> {
         let mut pos = self.start;
 
@@ -1,12 +1,56 @@
 const INPUT: &'static str = include_str!("./input");
 
 use std::collections::HashMap;
+use std::io::BufRead;
 
 fn main() {
+    if std::env::args().nth(1).as_deref() == Some("explore") {
+        // Explore the puzzle input unless a file is given
+        match std::env::args().nth(2) {
+            Some(path) => match std::fs::read_to_string(&path) {
+                Ok(input) => explore(&input),
+                Err(e) => println!("Could not read {}: {}", path, e),
+            },
+            None => explore(INPUT),
+        }
+        return;
+    }
+
     part1();
     part2();
 }
 
+fn explore(input: &str) {
+    let mut platform: Platform = input.into();
+
+    println!("{}", platform);
+    println!("Commands: n, w, s, e to tilt, c for a full cycle, q to quit");
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line.unwrap();
+
+        let directions = match line.trim() {
+            "n" => vec![(0, -1)],
+            "w" => vec![(-1, 0)],
+            "s" => vec![(0, 1)],
+            "e" => vec![(1, 0)],
+            "c" => vec![(0, -1), (-1, 0), (0, 1), (1, 0)],
+            "q" => break,
+            _ => {
+                println!("Unknown command: {}", line.trim());
+                continue;
+            }
+        };
+
+        for direction in directions {
+            platform.tilt(direction);
+        }
+
+        println!("{}", platform);
+        println!("Load: {}", platform.load());
+    }
+}
+
 fn part1() {
     let mut platform: Platform = INPUT.into();
     platform.tilt((0, -1));
@@ -163,6 +207,24 @@ impl Platform {
     }
 }
 
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for y in 1..=self.height {
+            for x in 1..=self.width {
+                let c = match self.rocks[&(x, y)] {
+                    Rock::Round => 'O',
+                    Rock::Cube => '#',
+                    Rock::None => '.',
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl From<&str> for Platform {
     fn from(value: &str) -> Self {
         let width = value.lines().next().unwrap().len() as isize;
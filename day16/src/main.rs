@@ -1,12 +1,59 @@
 const INPUT: &'static str = include_str!("./input");
 
 use std::collections::{HashMap, HashSet};
+use std::io::BufRead;
 
 fn main() {
+    if std::env::args().nth(1).as_deref() == Some("explore") {
+        // Explore the puzzle input unless a file is given
+        match std::env::args().nth(2) {
+            Some(path) => match std::fs::read_to_string(&path) {
+                Ok(input) => explore(&input),
+                Err(e) => println!("Could not read {}: {}", path, e),
+            },
+            None => explore(INPUT),
+        }
+        return;
+    }
+
     part1();
     part2();
 }
 
+fn explore(input: &str) {
+    let contraption: Contraption = input.into();
+
+    println!("Enter an edge start as `x y direction` (1-based, direction one of up, down, left, right), or q to quit");
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line.unwrap();
+
+        if line.trim() == "q" {
+            break;
+        }
+
+        let Some(start) = contraption.parse_edge_start(&line) else {
+            println!("Not an edge start: {}", line.trim());
+            continue;
+        };
+
+        let energized = contraption.energized_tiles(start);
+
+        for y in 1..=contraption.height {
+            for x in 1..=contraption.width {
+                if energized.contains(&(x, y)) {
+                    print!("#");
+                } else {
+                    print!(".");
+                }
+            }
+            println!();
+        }
+
+        println!("Energized: {}", energized.len());
+    }
+}
+
 fn part1() {
     let contraption: Contraption = INPUT.into();
 
@@ -48,6 +95,10 @@ struct  Contraption {
 
 impl Contraption {
     fn energized_from(&self, start: BeamState) -> usize {
+        self.energized_tiles(start).len()
+    }
+
+    fn energized_tiles(&self, start: BeamState) -> HashSet<(isize, isize)> {
         let mut all_next_states = vec![start];
 
         let mut visited = HashSet::new();
@@ -68,7 +119,35 @@ impl Contraption {
             all_next_states.append(&mut next_states);
         }
 
-        energized.len()
+        energized
+    }
+
+    fn parse_edge_start(&self, str: &str) -> Option<BeamState> {
+        let mut parts = str.split_whitespace();
+
+        let x: isize = parts.next()?.parse().ok()?;
+        let y: isize = parts.next()?.parse().ok()?;
+
+        let dir = match parts.next()? {
+            "up" => Direction::Up,
+            "down" => Direction::Down,
+            "left" => Direction::Left,
+            "right" => Direction::Right,
+            _ => return None,
+        };
+
+        let on_edge = match dir {
+            Direction::Down => y == 1 && (1..=self.width).contains(&x),
+            Direction::Up => y == self.height && (1..=self.width).contains(&x),
+            Direction::Right => x == 1 && (1..=self.height).contains(&y),
+            Direction::Left => x == self.width && (1..=self.height).contains(&y),
+        };
+
+        if on_edge {
+            Some(BeamState { pos: (x, y), dir })
+        } else {
+            None
+        }
     }
 }
 